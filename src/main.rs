@@ -1,32 +1,71 @@
 use std::{
-    fs::File,
-    io::{self, Read, Seek, SeekFrom},
-    path::PathBuf,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
-use attohttpc::header::{HeaderMap, HeaderName};
+use attohttpc::header::{HeaderMap, HeaderName, HeaderValue};
 use flate2::read::GzDecoder;
-use headers::{AcceptRanges, ContentLength, Header, HeaderMapExt, Range};
+use headers::{AcceptRanges, ContentLength, ContentRange, Header, HeaderMapExt, Range};
 use log::trace;
+use sha2::{Digest, Sha256};
 use simplelog::{ColorChoice, Config, LevelFilter, TermLogger, TerminalMode};
 use tee::TeeReader;
 
+/// The default cap on how many compressed bytes a single download may
+/// contain, guarding against a malicious or misreported `Content-Length`.
+const DEFAULT_MAX_DOWNLOAD_BYTES: u64 = 32 * 1024 * 1024 * 1024;
+
 fn main() -> Result<(), Error> {
     configure_logger();
-    let (url, out_dir) = args()?;
-    trace!("Testing against URL: '{url}'");
+    let args = args()?;
+    trace!("Testing against URL: '{}'", args.url);
 
-    let output_path = url
+    let output_path = args
+        .url
         .split('/')
         .last()
-        .map(|file_name| out_dir.join(file_name))
+        .map(|file_name| args.out_dir.join(file_name))
         .ok_or_else(|| Error::Usage {
-            message: format!("Could not construct file name from URL: {url}"),
+            message: format!("Could not construct file name from URL: {}", args.url),
         })?;
 
     println!("Output file path: {}", output_path.display());
 
-    let (status, headers, response) = attohttpc::get(&url).send()?.split();
+    if args.resume {
+        download_resumable(
+            &args.url,
+            &output_path,
+            args.max_download_bytes,
+            args.checksum.as_ref(),
+            args.transport_encoding,
+        )
+    } else {
+        download_streaming(
+            &args.url,
+            &output_path,
+            args.max_download_bytes,
+            args.checksum.as_ref(),
+            args.transport_encoding,
+        )
+    }
+}
+
+/// Downloads the archive at `url` in a single request, teeing the raw bytes to
+/// `output_path` as they arrive. A tar archive is decompressed and unpacked
+/// while the download is still in progress; a zip archive is unpacked from
+/// the saved file once the download finishes. This is the default mode: it is
+/// simple, but a network failure partway through means starting over from
+/// scratch.
+fn download_streaming(
+    url: &str,
+    output_path: &Path,
+    max_download_bytes: u64,
+    checksum: Option<&ExpectedDigest>,
+    transport_encoding: TransportEncoding,
+) -> Result<(), Error> {
+    let (status, headers, response) = request_with_encoding(url, transport_encoding).send()?.split();
 
     trace!("status: {status}");
     if !status.is_success() {
@@ -38,36 +77,710 @@ fn main() -> Result<(), Error> {
     let compressed_size = content_length(&headers)?;
     trace!("Compressed size: {compressed_size}");
 
-    let accepts_ranges = accepts_byte_ranges(&headers);
-    trace!("Accepts byte ranges: {accepts_ranges}");
+    // If the server transport-compressed the response, inflate it here,
+    // before the archive format is even sniffed, so the rest of the pipeline
+    // only ever sees the canonical (uncompressed-at-the-transport-layer)
+    // archive bytes. For a `.tar.gz`, this stacks underneath the tarball's
+    // own gzip layer rather than replacing it.
+    let mut raw_response = BufReader::new(response);
+    let transport_gzip = should_gunzip(&headers, raw_response.fill_buf()?);
+    let response: Box<dyn Read> = if transport_gzip {
+        Box::new(GzDecoder::new(raw_response))
+    } else {
+        Box::new(raw_response)
+    };
+
+    let mut response = BufReader::new(response);
+    let format = sniff_format(url, response.fill_buf()?)?;
+    trace!("Detected archive format: {format:?}");
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(output_path)?;
+    let mut hasher = Sha256::new();
+    let checked = guarded(response, Some(max_download_bytes), Some(&mut hasher));
+
+    match format {
+        Format::Tar(codec) => {
+            // `compressed_size` is the length of the wire response, which is
+            // meaningless as an archive-size estimate once transport gzip has
+            // been applied, so the isize-trailer round trip (which relies on
+            // it to compute a byte offset into the *un-transport-encoded*
+            // resource) is skipped entirely in that case.
+            let uncompressed_size = if transport_gzip {
+                None
+            } else {
+                match codec {
+                    Codec::Gzip => fetch_uncompressed_size(url, compressed_size),
+                    _ => Some(compressed_size),
+                }
+            };
+            let data: Box<dyn Read> = Box::new(TeeReader::new(checked, file));
+            let decoded = decode_tar(codec, data)?;
+            extract_atomically(output_path, |dest| {
+                unpack_tar_with_progress(decoded, uncompressed_size, dest)
+            })?;
+        }
+        Format::Zip => {
+            // The zip central directory lives at the end of the file, so it
+            // can't be extracted while streaming; tee it to disk first and
+            // reopen it for random access, same as the resumable download path.
+            //
+            // Same transport-gzip caveat as the tar branch's `uncompressed_size`:
+            // `compressed_size` is the wire length, meaningless as a progress
+            // total once transport gzip has been inflated away.
+            let zip_size = if transport_gzip { None } else { Some(compressed_size) };
+            let mut file = file;
+            io::copy(
+                &mut TeeReader::new(with_progress(checked, zip_size), &mut file),
+                &mut io::sink(),
+            )?;
+            clear_progress_line();
+            let file = File::open(output_path)?;
+            extract_atomically(output_path, |dest| extract_zip(file, dest))?;
+        }
+    }
+
+    verify_checksum(checksum, hasher)
+}
+
+/// Downloads the tarball at `url`, resuming a previous partial download if one
+/// is found at `output_path`. Because `GzDecoder` carries no resumable state,
+/// this mode downloads the compressed bytes to disk first and only
+/// decompresses and unpacks once the whole file is on disk.
+fn download_resumable(
+    url: &str,
+    output_path: &Path,
+    max_download_bytes: u64,
+    checksum: Option<&ExpectedDigest>,
+    transport_encoding: TransportEncoding,
+) -> Result<(), Error> {
+    let existing_len = output_path.metadata().map(|m| m.len()).unwrap_or(0);
+    let resuming = existing_len > 0 && server_accepts_byte_ranges(url)?;
+
+    // `Content-Encoding: gzip` makes the server's byte offsets meaningless,
+    // so a `Range` request and transport gzip negotiation can never be
+    // combined; resuming always asks for the identity encoding instead.
+    let transport_encoding = if resuming {
+        TransportEncoding::Identity
+    } else {
+        transport_encoding
+    };
+
+    let (status, headers, response) = {
+        let mut request = request_with_encoding(url, transport_encoding);
+        if resuming {
+            trace!("Found partial download of {existing_len} bytes, requesting the remainder");
+            request
+                .headers_mut()
+                .typed_insert(Range::bytes(existing_len..).unwrap());
+        }
+        request.send()?.split()
+    };
+
+    trace!("status: {status}");
+    if !status.is_success() {
+        return Err(Error::Http { status });
+    }
+
+    trace!("returned headers: {headers:?}");
+
+    let mut raw_response = BufReader::new(response);
+    let transport_gzip = should_gunzip(&headers, raw_response.fill_buf()?);
+    let response: Box<dyn Read> = if transport_gzip {
+        Box::new(GzDecoder::new(raw_response))
+    } else {
+        Box::new(raw_response)
+    };
+
+    // The cap is on the *whole* file, not just the bytes fetched this run, so
+    // a stale or oversized partial download left over from an earlier
+    // `--max-size` (or a corrupted leftover) is re-checked here too, rather
+    // than only ever guarding the Range delta. A `200 OK` restarts from
+    // scratch and discards the partial file, so there the whole file is just
+    // the bytes fetched this run.
+    let prior_len = if status == attohttpc::StatusCode::OK {
+        0
+    } else {
+        existing_len
+    };
+    if prior_len > max_download_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "Existing partial download ({existing_len} bytes) already exceeds the configured \
+                 {max_download_bytes}-byte limit"
+            ),
+        )
+        .into());
+    }
+    let response = guarded(response, Some(max_download_bytes - prior_len), None);
+
+    let mut file = match status {
+        attohttpc::StatusCode::PARTIAL_CONTENT => {
+            let total = content_range_total(&headers)?;
+            trace!("Server honored the range request; total size is {total} bytes");
+            // `existing_len` bytes are presumed to be a prefix of the full
+            // resource; if the server now reports a smaller total, the
+            // resource changed (or was truncated) since the partial download
+            // began, and appending to it would produce a corrupt file.
+            if total < existing_len {
+                return Err(Error::ResourceChanged {
+                    existing: existing_len,
+                    total,
+                });
+            }
+            let mut file = OpenOptions::new().read(true).append(true).open(output_path)?;
+            io::copy(
+                &mut TeeReader::new(with_progress(response, Some(total)), &mut file),
+                &mut io::sink(),
+            )?;
+            clear_progress_line();
+            file
+        }
+        attohttpc::StatusCode::OK => {
+            if resuming {
+                trace!("Server ignored the range request; restarting download from scratch");
+            }
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(output_path)?;
+            // Transport gzip makes `Content-Length` the wire size rather than
+            // the size of the bytes actually flowing out of `response`, same
+            // caveat as everywhere else `compressed_size` is used as a total.
+            let total = if transport_gzip {
+                None
+            } else {
+                content_length(&headers).ok()
+            };
+            io::copy(
+                &mut TeeReader::new(with_progress(response, total), &mut file),
+                &mut io::sink(),
+            )?;
+            clear_progress_line();
+            file
+        }
+        attohttpc::StatusCode::RANGE_NOT_SATISFIABLE => {
+            // The most common way to hit this: re-running `--continue`
+            // against a file a previous invocation already finished
+            // downloading, so `Range: bytes={existing_len}-` asks for bytes
+            // past the end of the resource. Treat that as "nothing left to
+            // fetch" and proceed straight to extraction, rather than
+            // surfacing a confusing 416 for what is actually success.
+            trace!("Server reports no bytes left to fetch; {existing_len} bytes already on disk");
+            File::open(output_path)?
+        }
+        _ => return Err(Error::Http { status }),
+    };
+
+    file.seek(SeekFrom::Start(0))?;
+    let format = sniff_format(url, &peek_bytes(&mut file)?)?;
+    trace!("Detected archive format: {format:?}");
+
+    let uncompressed_size = match format {
+        Format::Tar(Codec::Gzip) => Some(u32::from_le_bytes(load_isize(&mut file)?) as u64),
+        Format::Tar(_) => Some(file.metadata()?.len()),
+        Format::Zip => None,
+    };
+
+    let mut hasher = Sha256::new();
+
+    match format {
+        Format::Tar(codec) => {
+            let checked_file = guarded(file, None, Some(&mut hasher));
+            let data: Box<dyn Read> = Box::new(checked_file);
+            let decoded = decode_tar(codec, data)?;
+            extract_atomically(output_path, |dest| {
+                unpack_tar_with_progress(decoded, uncompressed_size, dest)
+            })?;
+        }
+        Format::Zip => {
+            // `zip::ZipArchive::new` seeks straight to the central directory
+            // and then jumps around per entry, so hashing can't be wired
+            // into the reader `extract_zip` uses the way it is for tar; hash
+            // the file sequentially first, same as `download_streaming`'s
+            // Zip branch does during its network `io::copy`.
+            let file_len = file.metadata()?.len();
+            io::copy(
+                &mut with_progress(guarded(&mut file, None, Some(&mut hasher)), Some(file_len)),
+                &mut io::sink(),
+            )?;
+            clear_progress_line();
+            file.seek(SeekFrom::Start(0))?;
+            extract_atomically(output_path, |dest| extract_zip(file, dest))?;
+        }
+    }
+
+    verify_checksum(checksum, hasher)
+}
+
+/// The archive container formats this tool knows how to extract, detected by
+/// sniffing the first few bytes of the downloaded data, with the URL's
+/// extension consulted only when those bytes match no known magic number.
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Tar(Codec),
+    Zip,
+}
+
+/// The compression codecs a `Format::Tar` archive may be wrapped in. `None`
+/// covers a bare, transport-gzip-only `.tar`: the archive content itself is
+/// uncompressed, which is exactly the case `TransportEncoding::Gzip` exists
+/// to make cheap to fetch.
+#[derive(Debug, Clone, Copy)]
+enum Codec {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+    Brotli,
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZIP_MAGIC: [u8; 2] = [0x50, 0x4B];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+const XZ_MAGIC: [u8; 6] = [0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00];
+
+/// Identifies an archive's container and codec from its leading "magic"
+/// bytes, falling back to the `url`'s extension for codecs (like brotli, or
+/// no codec at all) that have no reliable magic number of their own. A bare
+/// `.tar`'s `ustar` magic (at byte offset 257) is not checked here, so a bare
+/// `.tar` is only ever recognized by extension.
+fn sniff_format(url: &str, peek: &[u8]) -> Result<Format, Error> {
+    if peek.starts_with(&ZIP_MAGIC) {
+        Ok(Format::Zip)
+    } else if peek.starts_with(&GZIP_MAGIC) {
+        Ok(Format::Tar(Codec::Gzip))
+    } else if peek.starts_with(&ZSTD_MAGIC) {
+        Ok(Format::Tar(Codec::Zstd))
+    } else if peek.starts_with(&XZ_MAGIC) {
+        Ok(Format::Tar(Codec::Xz))
+    } else if url.ends_with(".br") || url.ends_with(".tar.br") {
+        Ok(Format::Tar(Codec::Brotli))
+    } else if url.ends_with(".tar") {
+        Ok(Format::Tar(Codec::None))
+    } else {
+        Err(Error::UnknownFormat)
+    }
+}
+
+#[cfg(test)]
+mod sniff_format_tests {
+    use super::*;
+
+    #[test]
+    fn detects_each_format_from_magic_bytes() {
+        let cases: &[(&str, &[u8], &str)] = &[
+            ("tarball.zip", &ZIP_MAGIC, "zip"),
+            ("tarball.tar.gz", &GZIP_MAGIC, "gzip"),
+            ("tarball.tar.zst", &ZSTD_MAGIC, "zstd"),
+            ("tarball.tar.xz", &XZ_MAGIC, "xz"),
+        ];
+
+        for (url, magic, label) in cases {
+            let format = sniff_format(url, magic).unwrap_or_else(|_| panic!("{label} should sniff"));
+            let detected = match format {
+                Format::Zip => "zip",
+                Format::Tar(Codec::Gzip) => "gzip",
+                Format::Tar(Codec::Zstd) => "zstd",
+                Format::Tar(Codec::Xz) => "xz",
+                Format::Tar(Codec::Brotli) => "brotli",
+                Format::Tar(Codec::None) => "tar",
+            };
+            assert_eq!(detected, *label, "unexpected format for {label}");
+        }
+    }
+
+    #[test]
+    fn falls_back_to_brotli_extension_with_no_magic_bytes() {
+        let format = sniff_format("https://example.com/tarball.tar.br", &[]).unwrap();
+        assert!(matches!(format, Format::Tar(Codec::Brotli)));
+    }
+
+    #[test]
+    fn falls_back_to_bare_tar_extension_with_no_magic_bytes() {
+        let format = sniff_format("https://example.com/tarball.tar", &[]).unwrap();
+        assert!(matches!(format, Format::Tar(Codec::None)));
+    }
+
+    #[test]
+    fn rejects_unrecognized_bytes_and_extension() {
+        assert!(sniff_format("https://example.com/tarball.mystery", &[0, 0, 0, 0]).is_err());
+    }
+}
 
-    let uncompressed_size = fetch_uncompressed_size(&url, compressed_size).unwrap();
+/// Reads the leading bytes of `file` without consuming them, for format
+/// sniffing, leaving the cursor back at the start.
+fn peek_bytes(file: &mut File) -> Result<[u8; 6], Error> {
+    let mut magic = [0u8; 6];
+    file.read_exact(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(magic)
+}
 
-    let file = File::create(&output_path)?;
-    let data = Box::new(TeeReader::new(response, file));
-    let decoded = GzDecoder::new(data);
+/// Wraps `source` in the streaming decoder matching `codec`, ready to be
+/// handed to `tar::Archive`. The zstd, xz, and brotli codecs are each gated
+/// behind a like-named Cargo feature, so a caller only pulls in the decoders
+/// they need; gzip is always available since it's also used for the
+/// `.tar.gz`-specific uncompressed-size optimization.
+fn decode_tar(codec: Codec, source: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+    match codec {
+        Codec::None => Ok(source),
+        Codec::Gzip => Ok(Box::new(GzDecoder::new(source))),
+        Codec::Zstd => decode_zstd(source),
+        Codec::Xz => decode_xz(source),
+        Codec::Brotli => decode_brotli(source),
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decode_zstd(source: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+    Ok(Box::new(zstd::stream::read::Decoder::new(source)?))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decode_zstd(_source: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+    Err(Error::UnsupportedCodec { name: "zstd" })
+}
+
+#[cfg(feature = "xz")]
+fn decode_xz(source: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+    Ok(Box::new(xz2::read::XzDecoder::new(source)))
+}
+
+#[cfg(not(feature = "xz"))]
+fn decode_xz(_source: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+    Err(Error::UnsupportedCodec { name: "xz" })
+}
+
+#[cfg(feature = "brotli")]
+fn decode_brotli(source: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+    Ok(Box::new(brotli::Decompressor::new(source, 4096)))
+}
 
-    let mut acc = 0u64;
-    let mut curr_per = 0f64;
-    let mut tarball = tar::Archive::new(ProgressRead::new(decoded, (), |_, read| {
-        // inelegant but usefully minimal.
-        acc += read as u64;
-        let percent_completed = 100.0 * (acc as f64 / uncompressed_size as f64);
-        if percent_completed > curr_per + 1.0 {
-            curr_per = percent_completed;
-            trace!(
-                "read {acc} / {uncompressed_size} bytes, (~{}%)",
-                percent_completed as u64
-            );
+#[cfg(not(feature = "brotli"))]
+fn decode_brotli(_source: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+    Err(Error::UnsupportedCodec { name: "brotli" })
+}
+
+/// Extracts a zip archive into `dest`, preserving unix permission bits where
+/// the archive records them.
+fn extract_zip<R: Read + Seek>(file: R, dest: &Path) -> Result<(), Error> {
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let Some(relative_path) = entry.enclosed_name() else {
+            trace!("Skipping zip entry with unsafe path: {:?}", entry.name());
+            continue;
+        };
+        let entry_path = dest.join(relative_path);
+        let wrap = |source: io::Error| Error::Extraction {
+            path: entry_path.clone(),
+            source,
+        };
+
+        if entry.is_dir() {
+            fs::create_dir_all(&entry_path).map_err(wrap)?;
+            continue;
+        }
+
+        if let Some(parent) = entry_path.parent() {
+            fs::create_dir_all(parent).map_err(wrap)?;
         }
-    }));
 
-    let out = output_path.with_file_name(output_path.to_str().unwrap().replace(".tar.gz", ""));
-    tarball.unpack(out)?;
+        let mut entry_file = File::create(&entry_path).map_err(wrap)?;
+        io::copy(&mut entry, &mut entry_file).map_err(wrap)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&entry_path, fs::Permissions::from_mode(mode)).map_err(wrap)?;
+        }
+    }
 
     Ok(())
 }
 
+/// Derives the directory an archive should be extracted into from the path
+/// of the archive file itself, stripping the archive extension.
+fn extraction_dir(output_path: &Path) -> PathBuf {
+    let name = output_path.to_str().unwrap();
+    let stripped = [".tar.gz", ".tar.zst", ".tar.xz", ".tar.br", ".tar", ".br", ".zip"]
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .unwrap_or(name);
+    output_path.with_file_name(stripped)
+}
+
+/// A freshly-created, empty sibling directory for an in-progress extraction,
+/// removed on drop unless the extraction finishes and is renamed into place.
+/// Keeping it next to the final destination (rather than in, say, a system
+/// temp directory) ensures the final rename is same-filesystem and therefore
+/// atomic.
+struct TempExtractionDir {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl TempExtractionDir {
+    /// Creates the temp directory, failing if it already exists. A directory
+    /// keyed only by `std::process::id()` can collide with one left behind
+    /// by a run that was killed before its `Drop` ran, if a later run
+    /// reuses the same PID; `create_dir` (unlike `create_dir_all`) rejects
+    /// that instead of silently layering new entries over stale ones.
+    fn create_sibling_of(destination: &Path) -> Result<Self, Error> {
+        let name = destination
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("extract");
+        let path = destination.with_file_name(format!("{name}.partial-{}", std::process::id()));
+        fs::create_dir(&path)?;
+        Ok(TempExtractionDir { path, keep: false })
+    }
+}
+
+#[cfg(test)]
+mod temp_extraction_dir_tests {
+    use super::*;
+
+    #[test]
+    fn refuses_to_reuse_a_stale_directory() {
+        let destination = std::env::temp_dir().join(format!("vnr-test-dest-{}", std::process::id()));
+        let stale = destination.with_file_name(format!(
+            "{}.partial-{}",
+            destination.file_name().unwrap().to_str().unwrap(),
+            std::process::id()
+        ));
+        fs::create_dir_all(&stale).unwrap();
+        fs::write(stale.join("leftover"), b"from a killed run").unwrap();
+
+        let result = TempExtractionDir::create_sibling_of(&destination);
+
+        assert!(result.is_err(), "stale directory should not be silently reused");
+        fs::remove_dir_all(&stale).unwrap();
+    }
+}
+
+impl Drop for TempExtractionDir {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// Extracts an archive atomically: `populate` unpacks into a fresh sibling
+/// temporary directory, which is only renamed into place over
+/// `extraction_dir(output_path)` once `populate` succeeds. If `populate`
+/// fails (or panics), the temporary directory is removed by
+/// `TempExtractionDir`'s `Drop` impl instead, so a mid-extraction error never
+/// leaves the destination partially written.
+fn extract_atomically(
+    output_path: &Path,
+    populate: impl FnOnce(&Path) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let destination = extraction_dir(output_path);
+    let mut temp = TempExtractionDir::create_sibling_of(&destination)?;
+
+    populate(&temp.path)?;
+
+    fs::rename(&temp.path, &destination)?;
+    temp.keep = true;
+
+    Ok(())
+}
+
+/// Wraps `source` so that every read through it renders a live progress line
+/// against `total` (or a spinner, if that's unknown), via the same
+/// `ProgressState`/`render_progress_line` machinery used for both the
+/// network fetch and the tar-unpack passes. The caller is responsible for
+/// calling `clear_progress_line` once it's done reading.
+fn with_progress<R: Read>(
+    source: R,
+    total: Option<u64>,
+) -> ProgressRead<R, ProgressState, impl FnMut(&ProgressState, &[u8]) -> io::Result<ProgressState>> {
+    ProgressRead::new(source, ProgressState::new(), move |prev, bytes| {
+        let mut next = prev.record(bytes.len() as u64);
+        if next.due_for_render(prev) {
+            render_progress_line(&next, total);
+            next.last_render = next.last_sample;
+        }
+        Ok(next)
+    })
+}
+
+/// Unpacks a decoded (decompressed) tar stream into `dest`, rendering a live
+/// progress line against `uncompressed_size` (or a spinner, if that's
+/// unknown) as it goes.
+///
+/// Unpacks entry-by-entry with `Entry::unpack_in` rather than the simpler
+/// `Archive::unpack`, so a failing entry can be attributed to its path in
+/// `Error::Extraction`. That gives up `Archive::unpack`'s two-pass handling
+/// of directory permissions and mtimes (normally deferred until after a
+/// directory's children are unpacked), so an archive with a read-only or
+/// out-of-order directory entry may now fail mid-extraction or end up with
+/// incorrect directory metadata.
+fn unpack_tar_with_progress<R: Read>(
+    decoded: R,
+    uncompressed_size: Option<u64>,
+    dest: &Path,
+) -> Result<(), Error> {
+    let mut tarball = tar::Archive::new(with_progress(decoded, uncompressed_size));
+
+    for entry in tarball.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        entry
+            .unpack_in(dest)
+            .map_err(|source| Error::Extraction { path, source })?;
+    }
+
+    // `entries` stops at the first end-of-archive block; read the rest of the
+    // stream too, so every downloaded byte reaches the tee and the hasher.
+    io::copy(&mut tarball.into_inner(), &mut io::sink())?;
+
+    clear_progress_line();
+
+    Ok(())
+}
+
+/// How often the progress line is allowed to redraw; reads happen far more
+/// often than this, so rendering on every one of them would be wasteful and
+/// flicker-prone.
+const PROGRESS_RENDER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How much weight the most recent read gets in the smoothed transfer-rate
+/// estimate, versus the rate estimate so far.
+const RATE_EWMA_ALPHA: f64 = 0.25;
+
+/// Tracks enough state between reads to render a transfer-rate- and
+/// ETA-aware progress line without re-scanning anything already read.
+#[derive(Clone, Copy)]
+struct ProgressState {
+    bytes: u64,
+    /// Bytes/second, smoothed with an exponentially-weighted moving average
+    /// over the last few reads, so a single slow or fast read doesn't make
+    /// the rate (and thus the ETA) jump around.
+    rate: f64,
+    last_sample: Instant,
+    last_render: Instant,
+}
+
+impl ProgressState {
+    fn new() -> Self {
+        let now = Instant::now();
+        ProgressState {
+            bytes: 0,
+            rate: 0.0,
+            last_sample: now,
+            last_render: now,
+        }
+    }
+
+    /// Folds in a read of `len` bytes, updating the byte count and the
+    /// smoothed rate estimate.
+    fn record(&self, len: u64) -> Self {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_sample).as_secs_f64().max(f64::EPSILON);
+        let instantaneous_rate = len as f64 / elapsed;
+        let rate = if self.bytes == 0 {
+            instantaneous_rate
+        } else {
+            RATE_EWMA_ALPHA * instantaneous_rate + (1.0 - RATE_EWMA_ALPHA) * self.rate
+        };
+
+        ProgressState {
+            bytes: self.bytes + len,
+            rate,
+            last_sample: now,
+            last_render: self.last_render,
+        }
+    }
+
+    /// Whether enough time has passed since `previous` last rendered that
+    /// this state is worth redrawing the progress line for.
+    fn due_for_render(&self, previous: &ProgressState) -> bool {
+        self.last_sample.duration_since(previous.last_render) >= PROGRESS_RENDER_INTERVAL
+    }
+}
+
+/// Renders a single rewriting status line (carriage-return, no newline) to
+/// the terminal, showing transferred/total size, percentage, transfer rate,
+/// and ETA; falls back to an indeterminate spinner when `total` is unknown.
+fn render_progress_line(state: &ProgressState, total: Option<u64>) {
+    let transferred = format_bytes(state.bytes as f64);
+    let rate = format_bytes(state.rate);
+
+    let line = match total.filter(|&total| total > 0) {
+        Some(total) => {
+            let percent = 100.0 * (state.bytes as f64 / total as f64);
+            let remaining = total.saturating_sub(state.bytes) as f64;
+            let eta = if state.rate > 0.0 {
+                format_eta(remaining / state.rate)
+            } else {
+                "--:--".to_string()
+            };
+            format!(
+                "{transferred} / {} ({percent:.1}%) at {rate}/s, ETA {eta}",
+                format_bytes(total as f64)
+            )
+        }
+        None => {
+            const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+            let frame = SPINNER[(state.bytes / (64 * 1024)) as usize % SPINNER.len()];
+            format!("{frame} {transferred} at {rate}/s")
+        }
+    };
+
+    print!("\r{line:<80}");
+    let _ = io::stdout().flush();
+}
+
+/// Clears a progress line previously drawn by `render_progress_line` by
+/// overwriting it with spaces.
+fn clear_progress_line() {
+    print!("\r{:<80}\r", "");
+    let _ = io::stdout().flush();
+}
+
+/// Formats a byte count (or rate, in bytes/second) in the largest unit that
+/// keeps it above 1, e.g. `"4.2 MiB"`.
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Formats a duration in seconds as `m:ss`, or `h:mm:ss` from one hour up.
+fn format_eta(seconds: f64) -> String {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return "--:--".to_string();
+    }
+
+    let total_seconds = seconds.round() as u64;
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let secs = total_seconds % 60;
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}")
+    } else {
+        format!("{minutes}:{secs:02}")
+    }
+}
+
 fn configure_logger() {
     TermLogger::init(
         LevelFilter::Trace,
@@ -78,13 +791,49 @@ fn configure_logger() {
     .expect("Set up the logger");
 }
 
-fn args() -> Result<(String, PathBuf), Error> {
-    let mut args = std::env::args();
-    let url = args.nth(1).ok_or_else(|| Error::Usage {
+/// The parsed command-line invocation.
+struct Args {
+    url: String,
+    out_dir: PathBuf,
+    /// Whether `--continue` was passed, i.e. whether to resume a partial
+    /// download rather than always streaming from scratch.
+    resume: bool,
+    /// The maximum number of compressed bytes to accept for a single
+    /// download, from `--max-size`, or `DEFAULT_MAX_DOWNLOAD_BYTES`.
+    max_download_bytes: u64,
+    /// The expected digest of the downloaded bytes, if one was passed as a
+    /// third positional argument.
+    checksum: Option<ExpectedDigest>,
+    /// Whether to negotiate HTTP transport-level gzip via `--accept-encoding`,
+    /// or `TransportEncoding::Gzip` by default.
+    transport_encoding: TransportEncoding,
+}
+
+fn args() -> Result<Args, Error> {
+    let mut raw: Vec<String> = std::env::args().skip(1).collect();
+
+    let resume = take_flag(&mut raw, "--continue");
+
+    let max_download_bytes = take_flag_value(&mut raw, "--max-size")?
+        .map(|value| {
+            value.parse::<u64>().map_err(|_| Error::Usage {
+                message: format!("Invalid --max-size value: '{value}'"),
+            })
+        })
+        .transpose()?
+        .unwrap_or(DEFAULT_MAX_DOWNLOAD_BYTES);
+
+    let transport_encoding = take_flag_value(&mut raw, "--accept-encoding")?
+        .map(|value| parse_transport_encoding(&value))
+        .transpose()?
+        .unwrap_or(TransportEncoding::Gzip);
+
+    let mut raw = raw.into_iter();
+    let url = raw.next().ok_or_else(|| Error::Usage {
         message: "Provide the URL to download.".into(),
     })?;
 
-    let out_dir = args
+    let out_dir = raw
         .next()
         .map(PathBuf::from)
         .filter(|path| path.is_dir())
@@ -92,7 +841,259 @@ fn args() -> Result<(String, PathBuf), Error> {
             message: "Provide a directory to place the downloaded file in".into(),
         })?;
 
-    Ok((url, out_dir))
+    let checksum = raw.next().map(|raw| parse_checksum(&raw)).transpose()?;
+
+    Ok(Args {
+        url,
+        out_dir,
+        resume,
+        max_download_bytes,
+        checksum,
+        transport_encoding,
+    })
+}
+
+/// Removes `flag` from `raw` if present, returning whether it was found.
+fn take_flag(raw: &mut Vec<String>, flag: &str) -> bool {
+    if let Some(index) = raw.iter().position(|arg| arg == flag) {
+        raw.remove(index);
+        true
+    } else {
+        false
+    }
+}
+
+/// Removes `flag` and the value following it from `raw` if present.
+fn take_flag_value(raw: &mut Vec<String>, flag: &str) -> Result<Option<String>, Error> {
+    let Some(index) = raw.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+
+    if index + 1 >= raw.len() {
+        return Err(Error::Usage {
+            message: format!("{flag} requires a value"),
+        });
+    }
+
+    raw.remove(index);
+    Ok(Some(raw.remove(index)))
+}
+
+/// An expected `sha256:<hex>` digest, parsed and ready to compare against.
+struct ExpectedDigest {
+    hex: String,
+    bytes: [u8; 32],
+}
+
+/// Parses a `sha256:<hex>` checksum spec, as passed as the optional third
+/// command-line argument.
+fn parse_checksum(raw: &str) -> Result<ExpectedDigest, Error> {
+    let hex_digest = raw.strip_prefix("sha256:").ok_or_else(|| Error::InvalidChecksum {
+        message: format!("Unsupported checksum spec '{raw}'; expected 'sha256:<hex>'"),
+    })?;
+
+    let bytes = hex::decode(hex_digest).map_err(|_| Error::InvalidChecksum {
+        message: format!("'{hex_digest}' is not valid hex"),
+    })?;
+
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| Error::InvalidChecksum {
+        message: "a sha256 digest must be 32 bytes".into(),
+    })?;
+
+    Ok(ExpectedDigest {
+        hex: hex_digest.to_lowercase(),
+        bytes,
+    })
+}
+
+/// Checks the finalized `hasher` against `expected`, if a checksum was
+/// requested at all.
+fn verify_checksum(expected: Option<&ExpectedDigest>, hasher: Sha256) -> Result<(), Error> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let actual = hasher.finalize();
+    if constant_time_eq(&expected.bytes, &actual) {
+        Ok(())
+    } else {
+        Err(Error::ChecksumMismatch {
+            expected: expected.hex.clone(),
+            actual: hex::encode(actual),
+        })
+    }
+}
+
+/// Compares two byte slices in time independent of where they first differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod checksum_tests {
+    use super::*;
+
+    #[test]
+    fn verifies_a_matching_digest_only_when_bytes_are_hashed_in_order() {
+        let expected = parse_checksum(&format!(
+            "sha256:{}",
+            hex::encode(Sha256::digest(b"hello, world"))
+        ))
+        .unwrap();
+
+        let mut sequential = Sha256::new();
+        sequential.update(b"hello, world");
+        assert!(verify_checksum(Some(&expected), sequential).is_ok());
+
+        // Same bytes, fed to the hasher out of order (e.g. the bug this
+        // guards against: hashing a zip in `zip-rs`'s seek order instead of
+        // sequential file order) must not verify.
+        let mut out_of_order = Sha256::new();
+        out_of_order.update(b"world, hello");
+        assert!(verify_checksum(Some(&expected), out_of_order).is_err());
+    }
+
+    #[test]
+    fn rejects_a_mismatched_digest() {
+        let expected = parse_checksum(&format!("sha256:{}", hex::encode([0u8; 32]))).unwrap();
+        let mut hasher = Sha256::new();
+        hasher.update(b"anything else");
+        assert!(verify_checksum(Some(&expected), hasher).is_err());
+    }
+
+    #[test]
+    fn skips_verification_when_no_checksum_was_requested() {
+        assert!(verify_checksum(None, Sha256::new()).is_ok());
+    }
+}
+
+/// Wraps `source` so that every byte read through it is, as applicable,
+/// counted against `max_bytes` (aborting the read once exceeded) and fed
+/// into `hasher`. Either guard can be omitted independently, so the same
+/// helper covers the download-time size cap and the decode-time checksum.
+fn guarded<R: Read>(
+    source: R,
+    max_bytes: Option<u64>,
+    mut hasher: Option<&mut Sha256>,
+) -> ProgressRead<R, u64, impl FnMut(&u64, &[u8]) -> io::Result<u64> + '_> {
+    ProgressRead::new(source, 0u64, move |&bytes_read, bytes| {
+        let bytes_read = bytes_read + bytes.len() as u64;
+        if let Some(limit) = max_bytes {
+            if bytes_read > limit {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Download exceeded the configured {limit}-byte limit"),
+                ));
+            }
+        }
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(bytes);
+        }
+        Ok(bytes_read)
+    })
+}
+
+/// Whether to negotiate HTTP transport-level gzip compression via
+/// `Accept-Encoding`, independent of whatever compression the archive format
+/// itself uses (e.g. a `.tar.gz`'s own gzip layer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransportEncoding {
+    Gzip,
+    Identity,
+}
+
+impl TransportEncoding {
+    fn header_value(self) -> &'static str {
+        match self {
+            TransportEncoding::Gzip => "gzip",
+            TransportEncoding::Identity => "identity",
+        }
+    }
+}
+
+/// Parses the `--accept-encoding` flag's value.
+fn parse_transport_encoding(raw: &str) -> Result<TransportEncoding, Error> {
+    match raw {
+        "gzip" => Ok(TransportEncoding::Gzip),
+        "identity" => Ok(TransportEncoding::Identity),
+        other => Err(Error::Usage {
+            message: format!("Unsupported --accept-encoding value '{other}'; expected 'gzip' or 'identity'"),
+        }),
+    }
+}
+
+/// Builds a `GET` request for `url` with an `Accept-Encoding` header
+/// reflecting `encoding`.
+fn request_with_encoding(url: &str, encoding: TransportEncoding) -> attohttpc::RequestBuilder {
+    let mut request = attohttpc::get(url);
+    request.headers_mut().insert(
+        HeaderName::from_static("accept-encoding"),
+        HeaderValue::from_static(encoding.header_value()),
+    );
+    request
+}
+
+/// Whether a response's `Content-Encoding` header indicates the server
+/// transport-compressed the body with gzip (`gzip` or the legacy `x-gzip`
+/// alias), as opposed to the tarball's own `.tar.gz` gzip layer, which is
+/// part of the archive content rather than the transport.
+///
+/// This is only a hint, not a guarantee that the body bytes are actually
+/// still gzip-compressed: some HTTP client configurations auto-decompress
+/// gzip responses themselves while leaving this header untouched. Callers
+/// that are about to wrap the body in a `GzDecoder` should confirm the
+/// leading `GZIP_MAGIC` bytes are actually present first, the same way
+/// `sniff_format` doesn't trust the URL alone for the archive format.
+fn is_gzip_transport_encoded(headers: &HeaderMap) -> bool {
+    headers
+        .get(HeaderName::from_static("content-encoding"))
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("gzip") || value.eq_ignore_ascii_case("x-gzip"))
+}
+
+/// Whether a response body should be inflated with a manual `GzDecoder`:
+/// the `Content-Encoding` header claims gzip *and* the leading bytes back
+/// that up. Some HTTP client configurations auto-decompress gzip responses
+/// before this code ever sees them while leaving the header in place, which
+/// would make a second, manual `GzDecoder` pass fail (or corrupt the
+/// archive); checking the actual bytes keeps that case from ever reaching
+/// `GzDecoder` a second time.
+fn should_gunzip(headers: &HeaderMap, peek: &[u8]) -> bool {
+    is_gzip_transport_encoded(headers) && peek.starts_with(&GZIP_MAGIC)
+}
+
+#[cfg(test)]
+mod should_gunzip_tests {
+    use super::*;
+
+    fn headers_with_content_encoding(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(HeaderName::from_static("content-encoding"), HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn gunzips_when_header_and_magic_bytes_agree() {
+        let headers = headers_with_content_encoding("gzip");
+        assert!(should_gunzip(&headers, &GZIP_MAGIC));
+    }
+
+    #[test]
+    fn skips_gunzip_when_header_claims_gzip_but_body_already_isnt() {
+        // e.g. an HTTP client that auto-decompressed the body already but
+        // left `Content-Encoding: gzip` in place.
+        let headers = headers_with_content_encoding("gzip");
+        assert!(!should_gunzip(&headers, b"\x50\x4B\x03\x04"));
+    }
+
+    #[test]
+    fn skips_gunzip_when_header_is_absent_even_if_bytes_look_like_gzip() {
+        let headers = HeaderMap::new();
+        assert!(!should_gunzip(&headers, &GZIP_MAGIC));
+    }
 }
 
 fn accepts_byte_ranges(headers: &HeaderMap) -> bool {
@@ -101,6 +1102,20 @@ fn accepts_byte_ranges(headers: &HeaderMap) -> bool {
         .is_some_and(|v| v == AcceptRanges::bytes())
 }
 
+/// Determines whether the server hosting `url` supports byte-range requests,
+/// via a `HEAD` request. This makes an extra round-trip to the server, but
+/// avoids re-downloading an entire file from scratch only to discover the
+/// `Range` header was ignored.
+fn server_accepts_byte_ranges(url: &str) -> Result<bool, Error> {
+    let (status, headers, _) = attohttpc::head(url).send()?.split();
+
+    if !status.is_success() {
+        return Err(Error::Http { status });
+    }
+
+    Ok(accepts_byte_ranges(&headers))
+}
+
 /// Determines the length of an HTTP response's content in bytes, using
 /// the HTTP `"Content-Length"` header.
 fn content_length(headers: &HeaderMap) -> Result<u64, Error> {
@@ -112,6 +1127,17 @@ fn content_length(headers: &HeaderMap) -> Result<u64, Error> {
         })
 }
 
+/// Determines the total (complete) length of the resource a `206 Partial
+/// Content` response belongs to, using the HTTP `"Content-Range"` header.
+fn content_range_total(headers: &HeaderMap) -> Result<u64, Error> {
+    headers
+        .typed_get::<ContentRange>()
+        .and_then(|v| v.bytes_len())
+        .ok_or_else(|| Error::MissingHeader {
+            name: ContentRange::name().to_owned(),
+        })
+}
+
 /// Determines the uncompressed size of a gzip file hosted at the specified
 /// URL by fetching just the metadata associated with the file. This makes
 /// an extra round-trip to the server, so it's only more efficient than just
@@ -140,7 +1166,10 @@ fn fetch_uncompressed_size(url: &str, len: u64) -> Option<u64> {
 /// more efficient than simply downloading the entire file up front.
 fn fetch_isize(url: &str, len: u64) -> Result<[u8; 4], Error> {
     let (status, headers, mut response) = {
-        let mut request = attohttpc::get(url);
+        // Identity only: `Content-Encoding: gzip` would make `len - 4..len`
+        // meaningless, since it no longer refers to a byte offset into the
+        // resource we're actually asking about.
+        let mut request = request_with_encoding(url, TransportEncoding::Identity);
         request
             .headers_mut()
             .typed_insert(Range::bytes(len - 4..len).unwrap());
@@ -175,7 +1204,8 @@ fn load_isize(file: &mut File) -> Result<[u8; 4], Error> {
     Ok(buf)
 }
 
-const USAGE: &str = "vnr <url> <output directory>";
+const USAGE: &str = "vnr <url> <output directory> [sha256:<hex>] [--continue] [--max-size <bytes>] \
+     [--accept-encoding <gzip|identity>]";
 
 #[derive(thiserror::Error)]
 enum Error {
@@ -202,6 +1232,33 @@ enum Error {
 
     #[error("Unexpected content length: {0}")]
     UnexpectedContentLength(u64),
+
+    #[error("Could not identify the archive format from its leading bytes")]
+    UnknownFormat,
+
+    #[error("Support for the '{name}' codec was not compiled in; enable its Cargo feature")]
+    UnsupportedCodec { name: &'static str },
+
+    #[error("Zip error: {source}")]
+    Zip {
+        #[from]
+        source: zip::result::ZipError,
+    },
+
+    #[error("Failed to extract '{}': {source}", path.display())]
+    Extraction { path: PathBuf, source: io::Error },
+
+    #[error("Checksum mismatch: expected sha256:{expected}, got sha256:{actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("Invalid checksum: {message}")]
+    InvalidChecksum { message: String },
+
+    #[error(
+        "The remote resource appears to have changed since the partial download began: it now \
+         reports a total size of {total} bytes, smaller than the {existing} bytes already on disk"
+    )]
+    ResourceChanged { existing: u64, total: u64 },
 }
 
 impl std::fmt::Debug for Error {
@@ -211,31 +1268,32 @@ impl std::fmt::Debug for Error {
 }
 
 /// A reader that reports incremental progress while reading.
-pub struct ProgressRead<R: Read, T, F: FnMut(&T, usize) -> T> {
+pub struct ProgressRead<R: Read, T, F: FnMut(&T, &[u8]) -> io::Result<T>> {
     source: R,
     accumulator: T,
     progress: F,
 }
 
-impl<R: Read, T, F: FnMut(&T, usize) -> T> Read for ProgressRead<R, T, F> {
+impl<R: Read, T, F: FnMut(&T, &[u8]) -> io::Result<T>> Read for ProgressRead<R, T, F> {
     /// Read some bytes from the underlying reader into the specified buffer,
     /// and report progress to the progress callback. The progress callback is
     /// passed the current value of the accumulator as its first argument and
-    /// the number of bytes read as its second argument. The result of the
+    /// the bytes just read as its second argument. The result of the
     /// progress callback is stored as the updated value of the accumulator,
-    /// to be passed to the next invocation of the callback.
+    /// to be passed to the next invocation of the callback, or propagated as
+    /// an I/O error if the callback rejects the read (e.g. a size cap).
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let len = self.source.read(buf)?;
         let new_accumulator = {
             let progress = &mut self.progress;
-            progress(&self.accumulator, len)
+            progress(&self.accumulator, &buf[..len])?
         };
         self.accumulator = new_accumulator;
         Ok(len)
     }
 }
 
-impl<R: Read, T, F: FnMut(&T, usize) -> T> ProgressRead<R, T, F> {
+impl<R: Read, T, F: FnMut(&T, &[u8]) -> io::Result<T>> ProgressRead<R, T, F> {
     /// Construct a new progress reader with the specified underlying reader,
     /// initial value for an accumulator, and progress callback.
     pub fn new(source: R, init: T, progress: F) -> ProgressRead<R, T, F> {
@@ -247,7 +1305,7 @@ impl<R: Read, T, F: FnMut(&T, usize) -> T> ProgressRead<R, T, F> {
     }
 }
 
-impl<R: Read + Seek, T, F: FnMut(&T, usize) -> T> Seek for ProgressRead<R, T, F> {
+impl<R: Read + Seek, T, F: FnMut(&T, &[u8]) -> io::Result<T>> Seek for ProgressRead<R, T, F> {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
         self.source.seek(pos)
     }